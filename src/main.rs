@@ -1,17 +1,19 @@
+use std::collections::HashMap;
 use std::io;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use ratatui::{
     buffer::Buffer,
     crossterm::{
         event::{
-            self, DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEventKind, MouseEventKind,
+            self, DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEventKind, MouseButton,
+            MouseEventKind,
         },
         execute,
     },
     layout::Rect,
     style::Color,
-    widgets::StatefulWidget,
+    widgets::{Block, Borders, Paragraph, StatefulWidget},
     DefaultTerminal,
 };
 
@@ -19,11 +21,37 @@ use rand::Rng;
 
 use rayon::prelude::*;
 
+use serde::{Deserialize, Serialize};
+
+// Default values for the runtime-tunable physics parameters (see `Simulation`).
 const DENSITY: f32 = 0.15f32;
 const GRAVITY_STRENGTH: f32 = 1.2f32;
 const FRICTION_PER_SECOND: f32 = 0.7f32;
-const UPPER_BLOCK: &'static str = "▀";
+const UPPER_BLOCK: &str = "▀";
+
+// Particle separations (in doubled-height cells) between which a constellation
+// line is drawn, fading from full brightness at NEAR to nothing at FAR.
+const CONSTELLATION_NEAR: f32 = 4.0f32;
+const CONSTELLATION_FAR: f32 = 20.0f32;
+// Base colour of the constellation lines before the brightness falloff.
+const CONSTELLATION_COLOR: (u8, u8, u8) = (120, 170, 255);
+
+// Fraction of speed retained when a particle bounces off a reflective edge.
+const RESTITUTION: f32 = 0.8f32;
+// Constant downward acceleration (cells/s²) applied in gravity-floor mode.
+const FLOOR_GRAVITY: f32 = 40.0f32;
 
+// Maximum number of persistent gravity wells that can be placed at once.
+const MAX_ATTRACTORS: usize = 16;
+// Particles emitted per second while a mouse button is held at the cursor.
+const SPAWN_RATE: f32 = 120.0f32;
+// Hard cap on the live population; the oldest particles are recycled past this.
+const MAX_PARTICLES: usize = 50_000;
+// A second mouse-down this close in time and space counts as a double-click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(350);
+const DOUBLE_CLICK_DISTANCE: f32 = 6.0f32;
+
+#[derive(Clone, Serialize, Deserialize)]
 struct Particle {
     x: f32,
     y: f32,
@@ -31,10 +59,49 @@ struct Particle {
     dy: f32,
 }
 
-#[derive(Clone, Copy)]
-struct Mouse {
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Attractor {
     x: f32,
     y: f32,
+    // Scales the pull; a negative value turns the well into a repeller.
+    strength: f32,
+}
+
+// Uniform spatial hash that buckets particle indices by cell so pair-wise
+// interaction modes only have to consider nearby particles instead of the full
+// O(n²) cross product. The cell size is chosen to equal the interaction radius,
+// so every particle within range of a query point lives in the query's own bin
+// or one of the eight surrounding it.
+struct SpatialGrid {
+    cell_size: f32,
+    bins: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    fn build(particles: &[Particle], cell_size: f32) -> Self {
+        let mut bins: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (i, particle) in particles.iter().enumerate() {
+            bins.entry(Self::bin(particle.x, particle.y, cell_size))
+                .or_default()
+                .push(i);
+        }
+        Self { cell_size, bins }
+    }
+
+    fn bin(x: f32, y: f32, cell_size: f32) -> (i32, i32) {
+        ((x / cell_size).floor() as i32, (y / cell_size).floor() as i32)
+    }
+
+    // Yields every particle index in the bin containing `(x, y)` and its eight
+    // neighbours — the candidate set for a radius-limited query.
+    fn neighbors(&self, x: f32, y: f32) -> impl Iterator<Item = usize> + '_ {
+        let (cx, cy) = Self::bin(x, y, self.cell_size);
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (cx + dx, cy + dy)))
+            .filter_map(move |key| self.bins.get(&key))
+            .flatten()
+            .copied()
+    }
 }
 
 struct SimulationWidget;
@@ -42,7 +109,67 @@ struct SimulationWidget;
 struct Simulation {
     particles: Vec<Particle>,
     time: Instant,
-    mouse: Option<Mouse>,
+    attractors: Vec<Attractor>,
+    constellation: bool,
+    // Cursor position (doubled-height coords) while a button is held, driving
+    // continuous emission; `None` when no button is down.
+    cursor: Option<(f32, f32)>,
+    // Fractional carry so the spawn rate stays framerate-independent.
+    spawn_acc: f32,
+    // Wrapping write cursor for the fixed-size recycle ring once at capacity.
+    recycle: usize,
+    // Reflective viewport edges instead of letting particles drift off-screen.
+    walls: bool,
+    // Constant downward pull. Particles settle and bounce along the floor only
+    // when `walls` is also on; otherwise they fall out past the bottom edge.
+    gravity: bool,
+    // Runtime-tunable physics parameters, seeded from the `const` defaults.
+    density: f32,
+    gravity_strength: f32,
+    friction_per_second: f32,
+    // Which tunable parameter the +/- keys currently act on.
+    selected: usize,
+    // Most recent frame duration, kept so the overlay can report an FPS figure.
+    last_delta_time: f32,
+}
+
+// The tunable parameters the overlay lists and the arrow keys cycle through.
+const PARAM_NAMES: [&str; 3] = ["density", "gravity", "friction"];
+
+impl Simulation {
+    // Moves the selection to the next/previous tunable parameter, wrapping.
+    fn cycle_selected(&mut self, forward: bool) {
+        let n = PARAM_NAMES.len();
+        self.selected = if forward {
+            (self.selected + 1) % n
+        } else {
+            (self.selected + n - 1) % n
+        };
+    }
+
+    // Nudges the selected parameter by its natural step. Density and friction are
+    // clamped to a sane range; gravity strength is unbounded and may go negative.
+    fn adjust_selected(&mut self, up: bool) {
+        let sign = if up { 1.0 } else { -1.0 };
+        match self.selected {
+            0 => self.density = (self.density + sign * 0.01).clamp(0.0, 1.0),
+            1 => self.gravity_strength += sign * 0.1,
+            _ => self.friction_per_second = (self.friction_per_second + sign * 0.02).clamp(0.01, 1.0),
+        }
+    }
+}
+
+// Serialisable snapshot of a simulation: particles, attractors and the tunable
+// parameters, so an arrangement can be resumed later. The wall-clock `time` and
+// the mode toggles (walls, gravity floor, constellation) are not saved; loading
+// keeps the running clock and the current toggles.
+#[derive(Serialize, Deserialize)]
+struct SimulationState {
+    particles: Vec<Particle>,
+    attractors: Vec<Attractor>,
+    density: f32,
+    gravity_strength: f32,
+    friction_per_second: f32,
 }
 
 fn generate_particles(density: f32, width: u16, height: u16) -> Vec<Particle> {
@@ -52,18 +179,118 @@ fn generate_particles(density: f32, width: u16, height: u16) -> Vec<Particle> {
     let count = ((w * h) as f32 * density) as usize;
     let mut particles = Vec::with_capacity(count);
 
+    let mut recycle = 0;
     for _ in 0..count {
-        particles.push(Particle {
-            x: rng.gen_range(0.0..width as f32),
-            y: rng.gen_range(0.0..height as f32),
-            dx: rng.gen_range(-1.0..1.0),
-            dy: rng.gen_range(-1.0..1.0),
-        });
+        let x = rng.gen_range(0.0..width as f32);
+        let y = rng.gen_range(0.0..height as f32);
+        spawn_particle_at(&mut particles, &mut recycle, x, y, &mut rng);
     }
 
     particles
 }
 
+// Pushes a new particle at `(x, y)` with a small random initial velocity. Once
+// the population hits `MAX_PARTICLES` the buffer stops growing and the oldest
+// slot is overwritten in O(1) via the wrapping `recycle` index, so holding the
+// emitter down doesn't trigger a full-vector shift each spawn.
+fn spawn_particle_at(
+    particles: &mut Vec<Particle>,
+    recycle: &mut usize,
+    x: f32,
+    y: f32,
+    rng: &mut impl Rng,
+) {
+    let particle = Particle {
+        x,
+        y,
+        dx: rng.gen_range(-1.0..1.0),
+        dy: rng.gen_range(-1.0..1.0),
+    };
+    if particles.len() >= MAX_PARTICLES {
+        *recycle %= MAX_PARTICLES;
+        particles[*recycle] = particle;
+        *recycle += 1;
+    } else {
+        particles.push(particle);
+    }
+}
+
+// Removes the attractor nearest `(x, y)`, but only when it lies within
+// `max_distance`, so a double-click on empty space clears nothing.
+fn remove_nearest_attractor(attractors: &mut Vec<Attractor>, x: f32, y: f32, max_distance: f32) {
+    let nearest = attractors
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (i, (a.x - x).powi(2) + (a.y - y).powi(2)))
+        .min_by(|(_, da), (_, db)| da.total_cmp(db));
+
+    if let Some((i, dist_sq)) = nearest {
+        if dist_sq <= max_distance * max_distance {
+            attractors.remove(i);
+        }
+    }
+}
+
+fn color_to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => (0, 0, 0),
+    }
+}
+
+// Writes `color` into the half-block cell covering the doubled-height pixel
+// `(x, y)`, keeping the brighter channel where something is already drawn so
+// overlapping lines accumulate rather than overwrite.
+fn blend_half_block(buf: &mut Buffer, x: u16, y: u16, color: (u8, u8, u8)) {
+    if let Some(cell) = buf.cell_mut((x, y / 2)) {
+        let existing = if y.is_multiple_of(2) {
+            color_to_rgb(cell.fg)
+        } else {
+            color_to_rgb(cell.bg)
+        };
+        let blended = Color::Rgb(
+            existing.0.max(color.0),
+            existing.1.max(color.1),
+            existing.2.max(color.2),
+        );
+        if y.is_multiple_of(2) {
+            cell.set_fg(blended);
+        } else {
+            cell.set_bg(blended);
+        }
+    }
+}
+
+// Rasterises a segment between two doubled-height pixels with Bresenham's
+// algorithm, blending `color` into every cell it crosses.
+fn draw_line(buf: &mut Buffer, x0: i32, y0: i32, x1: i32, y1: i32, color: (u8, u8, u8)) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut x = x0;
+    let mut y = y0;
+
+    loop {
+        if x >= 0 && y >= 0 {
+            blend_half_block(buf, x as u16, y as u16, color);
+        }
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
 impl StatefulWidget for SimulationWidget {
     type State = Simulation;
 
@@ -71,7 +298,22 @@ impl StatefulWidget for SimulationWidget {
         let curr_time = Instant::now();
         let delta_time = curr_time.saturating_duration_since(sim.time).as_secs_f32();
         sim.time = curr_time;
-        let mouse = sim.mouse.clone();
+        sim.last_delta_time = delta_time;
+
+        if let Some((cx, cy)) = sim.cursor {
+            sim.spawn_acc += SPAWN_RATE * delta_time;
+            let mut rng = rand::thread_rng();
+            while sim.spawn_acc >= 1.0 {
+                spawn_particle_at(&mut sim.particles, &mut sim.recycle, cx, cy, &mut rng);
+                sim.spawn_acc -= 1.0;
+            }
+        }
+
+        let attractors = sim.attractors.clone();
+        let constellation = sim.constellation;
+        let walls = sim.walls;
+        let gravity = sim.gravity;
+        let friction_per_second = sim.friction_per_second;
 
         buf.content.par_iter_mut().for_each(|cell| {
             if cell.symbol() != UPPER_BLOCK {
@@ -81,21 +323,15 @@ impl StatefulWidget for SimulationWidget {
             cell.set_bg(Color::Black);
         });
 
+        #[derive(Clone, Copy)]
         struct UnsafeBuf(*mut Buffer);
 
         unsafe impl Send for UnsafeBuf {}
 
         unsafe impl Sync for UnsafeBuf {}
 
-        impl Clone for UnsafeBuf {
-            fn clone(&self) -> Self {
-                Self(self.0.clone())
-            }
-        }
-
-        impl Copy for UnsafeBuf {}
-
         impl UnsafeBuf {
+            #[allow(clippy::mut_from_ref)]
             fn buf(&self) -> &mut Buffer {
                 unsafe { &mut *self.0 }
             }
@@ -104,24 +340,46 @@ impl StatefulWidget for SimulationWidget {
         let unsafe_buf = UnsafeBuf(buf as *mut Buffer);
 
         sim.particles.par_iter_mut().for_each(move |particle| {
-            if let Some(mouse) = mouse {
-                let dx = mouse.x - particle.x;
-                let dy = mouse.y - particle.y;
+            for attractor in attractors.iter() {
+                let dx = attractor.x - particle.x;
+                let dy = attractor.y - particle.y;
                 let distance = (dx * dx + dy * dy).sqrt();
 
                 if distance > 0.2 {
-                    let inv_gravity = GRAVITY_STRENGTH / distance;
+                    let inv_gravity = attractor.strength / distance;
                     particle.dx += dx * inv_gravity * 3.0;
                     particle.dy += dy * inv_gravity * 3.0;
                 }
             }
 
-            let friction = FRICTION_PER_SECOND.powf(delta_time);
+            let friction = friction_per_second.powf(delta_time);
             particle.dx *= friction;
             particle.dy *= friction;
+            if gravity {
+                particle.dy += FLOOR_GRAVITY * delta_time;
+            }
             particle.x += particle.dx * delta_time;
             particle.y += particle.dy * delta_time;
 
+            if walls {
+                let max_x = area.width as f32;
+                let max_y = (area.height * 2) as f32;
+                if particle.x < 0.0 {
+                    particle.x = 0.0;
+                    particle.dx = -particle.dx * RESTITUTION;
+                } else if particle.x >= max_x {
+                    particle.x = max_x - 0.001;
+                    particle.dx = -particle.dx * RESTITUTION;
+                }
+                if particle.y < 0.0 {
+                    particle.y = 0.0;
+                    particle.dy = -particle.dy * RESTITUTION;
+                } else if particle.y >= max_y {
+                    particle.y = max_y - 0.001;
+                    particle.dy = -particle.dy * RESTITUTION;
+                }
+            }
+
             if particle.x < area.width as f32
                 && particle.y < (area.height * 2) as f32
                 && particle.x >= 0.0f32
@@ -133,7 +391,7 @@ impl StatefulWidget for SimulationWidget {
                 let green = (particle.y / ((area.height * 2) as f32) * 255.0 * 0.8) as u8;
                 let blue = (255.0 * 0.6) as u8;
                 if let Some(cell) = unsafe_buf.buf().cell_mut((x, y / 2)) {
-                    if y % 2 == 0 {
+                    if y.is_multiple_of(2) {
                         cell.set_fg(Color::Rgb(red, green, blue));
                     } else {
                         cell.set_bg(Color::Rgb(red, green, blue));
@@ -141,7 +399,78 @@ impl StatefulWidget for SimulationWidget {
                 }
             }
         });
+
+        // Rebuild the spatial grid once per tick, after integration and before
+        // the interaction pass, so every pairwise consumer can share it.
+        let grid = SpatialGrid::build(&sim.particles, CONSTELLATION_FAR);
+
+        if constellation {
+            let particles = &sim.particles;
+            for (i, a) in particles.iter().enumerate() {
+                for j in grid.neighbors(a.x, a.y) {
+                    // Only handle each unordered pair once; the reverse lookup
+                    // will find this one from the other side.
+                    if j <= i {
+                        continue;
+                    }
+                    let b = &particles[j];
+                    let dx = b.x - a.x;
+                    let dy = b.y - a.y;
+                    let distance = (dx * dx + dy * dy).sqrt();
+
+                    if (CONSTELLATION_NEAR..=CONSTELLATION_FAR).contains(&distance) {
+                        let brightness = (CONSTELLATION_FAR - distance)
+                            / (CONSTELLATION_FAR - CONSTELLATION_NEAR);
+                        let color = (
+                            (CONSTELLATION_COLOR.0 as f32 * brightness) as u8,
+                            (CONSTELLATION_COLOR.1 as f32 * brightness) as u8,
+                            (CONSTELLATION_COLOR.2 as f32 * brightness) as u8,
+                        );
+                        draw_line(
+                            buf, a.x as i32, a.y as i32, b.x as i32, b.y as i32, color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn state_file_path() -> io::Result<std::path::PathBuf> {
+    let mut dir = dirs::config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    dir.push("tpart");
+    dir.push("state.json");
+    Ok(dir)
+}
+
+fn save_state(sim: &Simulation) -> io::Result<()> {
+    let path = state_file_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    let state = SimulationState {
+        particles: sim.particles.clone(),
+        attractors: sim.attractors.clone(),
+        density: sim.density,
+        gravity_strength: sim.gravity_strength,
+        friction_per_second: sim.friction_per_second,
+    };
+    let json = serde_json::to_string(&state)?;
+    std::fs::write(path, json)
+}
+
+fn load_state(sim: &mut Simulation) -> io::Result<()> {
+    let path = state_file_path()?;
+    let json = std::fs::read_to_string(path)?;
+    let state: SimulationState = serde_json::from_str(&json)?;
+    sim.particles = state.particles;
+    sim.attractors = state.attractors;
+    sim.density = state.density;
+    sim.gravity_strength = state.gravity_strength;
+    sim.friction_per_second = state.friction_per_second;
+    sim.recycle = 0;
+    Ok(())
 }
 
 fn run(mut terminal: DefaultTerminal) -> io::Result<()> {
@@ -154,12 +483,55 @@ fn run(mut terminal: DefaultTerminal) -> io::Result<()> {
     let mut simulation = Simulation {
         particles,
         time: Instant::now(),
-        mouse: None,
+        attractors: Vec::new(),
+        constellation: false,
+        cursor: None,
+        spawn_acc: 0.0,
+        recycle: 0,
+        walls: false,
+        gravity: false,
+        density: DENSITY,
+        gravity_strength: GRAVITY_STRENGTH,
+        friction_per_second: FRICTION_PER_SECOND,
+        selected: 0,
+        last_delta_time: 0.0,
     };
 
+    // Remembers the previous mouse-down (time, position, and whether it placed a
+    // well) so two quick clicks in the same spot can be recognised as a
+    // double-click and the first click's placement undone.
+    let mut last_down: Option<(Instant, f32, f32, bool)> = None;
+
     loop {
         terminal.draw(|frame| {
             frame.render_stateful_widget(SimulationWidget, frame.area(), &mut simulation);
+
+            let fps = if simulation.last_delta_time > 0.0 {
+                1.0 / simulation.last_delta_time
+            } else {
+                0.0
+            };
+            let values = [
+                simulation.density,
+                simulation.gravity_strength,
+                simulation.friction_per_second,
+            ];
+            let mut lines = vec![format!("fps: {:.1}", fps)];
+            for (i, name) in PARAM_NAMES.iter().enumerate() {
+                let marker = if i == simulation.selected { '>' } else { ' ' };
+                lines.push(format!("{} {}: {:.3}", marker, name, values[i]));
+            }
+            let overlay = Paragraph::new(lines.join("\n"))
+                .block(Block::default().borders(Borders::ALL).title("params"));
+
+            let full = frame.area();
+            let area = Rect {
+                x: full.x,
+                y: full.y,
+                width: full.width.min(24),
+                height: full.height.min(6),
+            };
+            frame.render_widget(overlay, area);
         })?;
 
         if event::poll(
@@ -173,19 +545,73 @@ fn run(mut terminal: DefaultTerminal) -> io::Result<()> {
                     KeyCode::Backspace => {
                         let size = terminal.size()?;
                         simulation.particles =
-                            generate_particles(DENSITY, size.width, size.height * 2);
+                            generate_particles(simulation.density, size.width, size.height * 2);
+                        simulation.recycle = 0;
                         continue;
                     }
+                    KeyCode::Char('s') => {
+                        let _ = save_state(&simulation);
+                    }
+                    KeyCode::Char('l') => {
+                        let _ = load_state(&mut simulation);
+                    }
+                    KeyCode::Char('c') => {
+                        simulation.constellation = !simulation.constellation;
+                    }
+                    KeyCode::Char('b') => {
+                        simulation.walls = !simulation.walls;
+                    }
+                    KeyCode::Char('g') => {
+                        simulation.gravity = !simulation.gravity;
+                    }
+                    KeyCode::Up => simulation.cycle_selected(false),
+                    KeyCode::Down => simulation.cycle_selected(true),
+                    KeyCode::Char('+') | KeyCode::Char('=') => simulation.adjust_selected(true),
+                    KeyCode::Char('-') => simulation.adjust_selected(false),
                     _ => {}
                 },
                 event::Event::Mouse(m) => {
-                    if matches!(m.kind, MouseEventKind::Down(_) | MouseEventKind::Drag(_)) {
-                        simulation.mouse = Some(Mouse {
-                            x: m.column as f32,
-                            y: (m.row * 2) as f32,
+                    if let MouseEventKind::Down(button) = m.kind {
+                        let x = m.column as f32;
+                        let y = (m.row * 2) as f32;
+                        let now = Instant::now();
+                        simulation.cursor = Some((x, y));
+
+                        let double_click = last_down.is_some_and(|(t, px, py, _)| {
+                            now.saturating_duration_since(t) <= DOUBLE_CLICK_TIME
+                                && ((x - px).powi(2) + (y - py).powi(2)).sqrt()
+                                    <= DOUBLE_CLICK_DISTANCE
                         });
-                    } else if matches!(m.kind, MouseEventKind::Up(_)) {
-                        simulation.mouse = None;
+
+                        if double_click {
+                            // This down completes a double-click: undo the well
+                            // the first down placed, then remove the existing
+                            // well under the cursor (if the click landed near one).
+                            if matches!(last_down, Some((_, _, _, true))) {
+                                simulation.attractors.pop();
+                            }
+                            remove_nearest_attractor(
+                                &mut simulation.attractors,
+                                x,
+                                y,
+                                DOUBLE_CLICK_DISTANCE,
+                            );
+                            last_down = None;
+                        } else {
+                            let placed = simulation.attractors.len() < MAX_ATTRACTORS;
+                            if placed {
+                                let strength = match button {
+                                    MouseButton::Right => -simulation.gravity_strength,
+                                    _ => simulation.gravity_strength,
+                                };
+                                simulation.attractors.push(Attractor { x, y, strength });
+                            }
+                            last_down = Some((now, x, y, placed));
+                        }
+                    } else if let MouseEventKind::Drag(_) = m.kind {
+                        simulation.cursor = Some((m.column as f32, (m.row * 2) as f32));
+                    } else if let MouseEventKind::Up(_) = m.kind {
+                        simulation.cursor = None;
                     }
                 }
                 _ => {}